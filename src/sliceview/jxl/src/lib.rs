@@ -1,26 +1,221 @@
 use std::ptr;
-use std::alloc::{alloc, dealloc, Layout};
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::UnsafeCell;
 use std::slice;
 
 use jxl_oxide::{FrameBuffer, JxlImage, PixelFormat};
 
-#[no_mangle]
-pub fn malloc(size: usize) -> *mut u8 {
-    let layout = Layout::from_size_align(size, std::mem::align_of::<u8>()).unwrap();
-    unsafe {
-        let ptr = alloc(layout);
+// Linear-memory region reserved up front for the arena. Neuroglancer issues a
+// steady stream of tile requests, so we pay for the reservation once instead of
+// churning the allocator on every `decode`.
+const ARENA_CAPACITY: usize = 64 * 1024 * 1024;
+
+// Alignment the backing region is placed at, and the largest alignment the
+// arena can satisfy. Comfortably covers every Rust/jxl-oxide type we hand out
+// (including 16-byte WASM SIMD vectors).
+const REGION_ALIGN: usize = 64;
+
+// Default alignment for the host-facing u8/u16/f32 buffers.
+const ARENA_ALIGN: usize = 16;
+
+// Backing storage, aligned to `REGION_ALIGN` so an `align`-bumped offset is an
+// `align`-aligned absolute address. Placed in `.bss`, so it starts fully zeroed.
+#[repr(C, align(64))]
+struct Region([u8; ARENA_CAPACITY]);
+
+// The reusable bump arena that backs every allocation in this module. It is
+// wired as the `#[global_allocator]` so jxl-oxide's own transient allocations
+// also come from it, eliminating the per-tile allocator churn and fragmentation
+// the original `malloc`/`free` dance caused. `offset` is the bump cursor;
+// `watermark` is the high-water mark, below which memory may be dirty and at or
+// above which it is still zero. `dealloc` reclaims only the most recent
+// allocation (LIFO); everything else is reclaimed in bulk by `reset_arena`.
+struct BumpArena {
+    region: UnsafeCell<Region>,
+    offset: UnsafeCell<usize>,
+    watermark: UnsafeCell<usize>,
+}
+
+// The decoder runs single-threaded inside one WASM instance, so the interior
+// mutability behind `UnsafeCell` is never touched concurrently.
+unsafe impl Sync for BumpArena {}
+
+impl BumpArena {
+    const fn new() -> Self {
+        BumpArena {
+            region: UnsafeCell::new(Region([0u8; ARENA_CAPACITY])),
+            offset: UnsafeCell::new(0),
+            watermark: UnsafeCell::new(0),
+        }
+    }
+
+    // Hand out `size` bytes at an `align`-aligned start by bumping the cursor.
+    // O(1) — no size-class rounding and no free-list scan. Returns null when the
+    // alignment exceeds the region's or the reserved region is exhausted.
+    unsafe fn bump(&self, size: usize, align: usize) -> *mut u8 {
+        if align > REGION_ALIGN {
+            return ptr::null_mut();
+        }
+        let offset = &mut *self.offset.get();
+        let start = (*offset).next_multiple_of(align.max(1));
+        let end = match start.checked_add(size) {
+            Some(end) if end <= ARENA_CAPACITY => end,
+            _ => return ptr::null_mut(),
+        };
+        *offset = end;
+        let watermark = &mut *self.watermark.get();
+        if end > *watermark {
+            *watermark = end;
+        }
+        (*self.region.get()).0.as_mut_ptr().add(start)
+    }
+
+    // As `bump`, but the range is returned zeroed. Fast path: the tail sitting
+    // at or above the old watermark is freshly reserved memory that is still
+    // zero, so only the overlap with already-written memory needs a memset.
+    unsafe fn bump_zeroed(&self, size: usize, align: usize) -> *mut u8 {
+        let prev_watermark = *self.watermark.get();
+        let ptr = self.bump(size, align);
         if ptr.is_null() {
-            panic!("Memory allocation failed");
+            return ptr;
+        }
+        let base = (*self.region.get()).0.as_ptr();
+        let start = ptr as usize - base as usize;
+        if start < prev_watermark {
+            let dirty = prev_watermark.min(start + size) - start;
+            ptr::write_bytes(ptr, 0, dirty);
         }
         ptr
     }
+
+    // LIFO reclaim: rewind the cursor only when `ptr` is the most recent
+    // allocation; anything else waits for `reset_arena`. Keeps free O(1) and
+    // lets a short-lived allocation freed before the next one give its memory
+    // straight back. A `Vec` realloc bumps a new block above the old one, so the
+    // old block is not the most recent and stays in use until `reset_arena`.
+    unsafe fn reclaim(&self, ptr: *mut u8, size: usize) {
+        let base = (*self.region.get()).0.as_ptr();
+        let start = ptr as usize - base as usize;
+        let offset = &mut *self.offset.get();
+        if start + size == *offset {
+            *offset = start;
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for BumpArena {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.bump(layout.size(), layout.align())
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        self.bump_zeroed(layout.size(), layout.align())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.reclaim(ptr, layout.size());
+    }
+}
+
+#[global_allocator]
+static ARENA: BumpArena = BumpArena::new();
+
+// Bulk-free the arena between frames: rewind the bump cursor in one step,
+// reclaiming every allocation — jxl-oxide's transient buffers and the host's
+// input/output alike. The watermark is left intact so memory reused below it is
+// still zeroed by `malloc_zeroed`. The host MUST be done reading the previous
+// frame's output and have released its input buffer before calling this; those
+// pointers dangle afterwards.
+#[no_mangle]
+pub fn reset_arena() {
+    unsafe {
+        *ARENA.offset.get() = 0;
+    }
+}
+
+#[no_mangle]
+pub fn malloc(size: usize) -> *mut u8 {
+    let layout = Layout::from_size_align(size, ARENA_ALIGN).unwrap();
+    let ptr = unsafe { std::alloc::alloc(layout) };
+    if ptr.is_null() {
+        panic!("Memory allocation failed");
+    }
+    ptr
+}
+
+// Like `malloc`, but the returned region is guaranteed zeroed, taking the
+// arena's fast path for freshly grown memory so no separate memset is paid.
+#[no_mangle]
+pub fn malloc_zeroed(size: usize) -> *mut u8 {
+    let layout = Layout::from_size_align(size, ARENA_ALIGN).unwrap();
+    let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+    if ptr.is_null() {
+        panic!("Memory allocation failed");
+    }
+    ptr
 }
 
 #[no_mangle]
 pub fn free(ptr: *mut u8, size: usize) {
-    let layout = Layout::from_size_align(size, std::mem::align_of::<u8>()).unwrap();
+    let layout = Layout::from_size_align(size, ARENA_ALIGN).unwrap();
     unsafe {
-        dealloc(ptr, layout);
+        std::alloc::dealloc(ptr, layout);
+    }
+}
+
+// A fixed-capacity, arena-backed output buffer with a `Vec`-like push API, so
+// the decode entry points build their result in place without a per-call heap
+// allocation. The caller sizes it through the `output_size` argument of each
+// decode entry point; pushes beyond that capacity are refused and
+// flagged rather than writing past the allocation into adjacent arena memory.
+struct ArenaBuf {
+    ptr: *mut u8,
+    len: usize,
+    cap: usize,
+    overflow: bool,
+}
+
+impl ArenaBuf {
+    fn new(size: usize) -> Option<ArenaBuf> {
+        let layout = Layout::from_size_align(size, ARENA_ALIGN).ok()?;
+        let ptr = unsafe { std::alloc::alloc(layout) };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(ArenaBuf { ptr, len: 0, cap: size, overflow: false })
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, byte: u8) {
+        if self.len < self.cap {
+            unsafe {
+                *self.ptr.add(self.len) = byte;
+            }
+            self.len += 1;
+        } else {
+            // A byte beyond the caller-declared `output_size`: record it and
+            // drop the write so we never corrupt neighbouring arena memory.
+            self.overflow = true;
+        }
+    }
+
+    #[inline]
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.push(byte);
+        }
+    }
+
+    // Pointer to the packed output, or null if any push overflowed `output_size`
+    // so an under-sized buffer fails loudly instead of returning a truncated,
+    // partially-corrupt result.
+    fn finish(&self) -> *const u8 {
+        if self.overflow {
+            ptr::null()
+        } else {
+            self.ptr
+        }
     }
 }
 
@@ -42,6 +237,26 @@ pub fn height_and_width(ptr: *mut u8, input_size: usize) -> i64 {
     }
 }
 
+#[no_mangle]
+pub fn dimensions_at_level(ptr: *mut u8, input_size: usize, reduction: u32) -> i64 {
+    if ptr.is_null() || input_size == 0 {
+        return -1;
+    }
+
+    let data: &[u8] = unsafe {
+        slice::from_raw_parts(ptr, input_size)
+    };
+
+    match JxlImage::builder().read(data) {
+        Ok(image) => {
+            let height = reduced_size(image.image_header().size.height as usize, reduction) as i64;
+            let width = reduced_size(image.image_header().size.width as usize, reduction) as i64;
+            ((height << 31) | (width & 0x7fffffff)) as i64
+        }
+        Err(_) => -2,
+    }
+}
+
 #[no_mangle]
 pub fn decode(ptr: *mut u8, input_size: usize, output_size: usize) -> *const u8 {
     if ptr.is_null() || input_size == 0 || output_size == 0 {
@@ -57,7 +272,10 @@ pub fn decode(ptr: *mut u8, input_size: usize, output_size: usize) -> *const u8
         Err(_image) => return std::ptr::null_mut(),
     };
 
-    let mut output_buffer = Vec::with_capacity(output_size);
+    let mut output_buffer = match ArenaBuf::new(output_size) {
+        Some(buffer) => buffer,
+        None => return ptr::null(),
+    };
 
     for keyframe_idx in 0..image.num_loaded_keyframes() {
         let frame = match image.render_frame(keyframe_idx) {
@@ -74,36 +292,559 @@ pub fn decode(ptr: *mut u8, input_size: usize, output_size: usize) -> *const u8
         stream.write_to_buffer(fb.buf_mut());
 
         match image.pixel_format() {
-            PixelFormat::Gray => {
-                for pixel in fb.buf() {
-                    let value = (pixel * 255.0).clamp(0.0, 255.0).round() as u8;
-                    output_buffer.push(value);
+            PixelFormat::Rgba => {
+                for pixel in fb.buf().chunks(stream.channels() as usize) {
+                    for &sample in &pixel[..3] {
+                        let value = (sample * 255.0).clamp(0.0, 255.0).round() as u8;
+                        output_buffer.push(value);
+                    }
+                    output_buffer.push(255);  // Alpha channel set to fully opaque
                 }
-            },
-            PixelFormat::Rgb => {
+            }
+            // Every other format — Gray, GrayA, Rgb, and images with extra
+            // channels (depth, masks, segmentation IDs) — is packed the same
+            // way: interleave each rendered channel in order and preserve the
+            // frame's channel count. Only Rgba injects a synthetic alpha.
+            _ => {
                 for pixel in fb.buf() {
                     let value = (pixel * 255.0).clamp(0.0, 255.0).round() as u8;
                     output_buffer.push(value);
                 }
             }
+        }
+    }
+
+    // The arena owns the buffer; the host frees it via `reset_arena`/`free`.
+    output_buffer.finish()
+}
+
+// Fixed-layout metadata header written by `read_metadata`. Laid out `repr(C)`
+// with `u32` fields so the JS side can read it with a single DataView.
+#[repr(C)]
+struct Metadata {
+    width: u32,
+    height: u32,
+    bits_per_sample: u32,
+    color_channels: u32,
+    extra_channels: u32,
+    keyframes: u32,
+    colorspace: u32,
+}
+
+// Colorspace enum tags shared with the JS side.
+const COLORSPACE_GRAY: u32 = 0;
+const COLORSPACE_RGB: u32 = 1;
+const COLORSPACE_OTHER: u32 = 2;
+
+#[no_mangle]
+pub fn read_metadata(ptr: *mut u8, input_size: usize, out_ptr: *mut u8) -> i32 {
+    if ptr.is_null() || input_size == 0 || out_ptr.is_null() {
+        return -1;
+    }
+
+    let data: &[u8] = unsafe {
+        slice::from_raw_parts(ptr, input_size)
+    };
+
+    let image = match JxlImage::builder().read(data) {
+        Ok(image) => image,
+        Err(_) => return -2,
+    };
+
+    let header = image.image_header();
+    let (color_channels, colorspace) = match image.pixel_format() {
+        PixelFormat::Gray | PixelFormat::Graya => (1, COLORSPACE_GRAY),
+        PixelFormat::Rgb | PixelFormat::Rgba => (3, COLORSPACE_RGB),
+        _ => (header.metadata.encoded_color_channels as u32, COLORSPACE_OTHER),
+    };
+
+    let metadata = Metadata {
+        width: header.size.width,
+        height: header.size.height,
+        bits_per_sample: header.metadata.bit_depth.bits_per_sample(),
+        color_channels,
+        extra_channels: header.metadata.ec_info.len() as u32,
+        keyframes: image.num_loaded_keyframes() as u32,
+        colorspace,
+    };
+
+    let bytes = unsafe {
+        slice::from_raw_parts(
+            (&metadata as *const Metadata) as *const u8,
+            std::mem::size_of::<Metadata>(),
+        )
+    };
+    let out = unsafe { slice::from_raw_parts_mut(out_ptr, bytes.len()) };
+    out.copy_from_slice(bytes);
+
+    bytes.len() as i32
+}
+
+// Pack one normalized [0, 1] float sample into `output_buffer` at the
+// requested output depth: 8-bit unsigned, 16-bit little-endian unsigned, or
+// raw 32-bit float bytes. Returns false for an unsupported depth.
+fn push_sample(output_buffer: &mut ArenaBuf, sample: f32, out_bits: u32) -> bool {
+    match out_bits {
+        8 => {
+            output_buffer.push((sample * 255.0).clamp(0.0, 255.0).round() as u8);
+        }
+        16 => {
+            let value = (sample * 65535.0).clamp(0.0, 65535.0).round() as u16;
+            output_buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        32 => {
+            output_buffer.extend_from_slice(&sample.to_le_bytes());
+        }
+        _ => return false,
+    }
+    true
+}
+
+#[no_mangle]
+pub fn source_bits_per_sample(ptr: *mut u8, input_size: usize) -> i32 {
+    if ptr.is_null() || input_size == 0 {
+        return -1;
+    }
+
+    let data: &[u8] = unsafe {
+        slice::from_raw_parts(ptr, input_size)
+    };
+
+    match JxlImage::builder().read(data) {
+        Ok(image) => image.image_header().metadata.bit_depth.bits_per_sample() as i32,
+        Err(_) => -2,
+    }
+}
+
+#[no_mangle]
+pub fn decode_typed(
+    ptr: *mut u8,
+    input_size: usize,
+    output_size: usize,
+    out_bits: u32,
+) -> *const u8 {
+    if ptr.is_null() || input_size == 0 || output_size == 0 {
+        return ptr::null();
+    }
+
+    let data: &[u8] = unsafe {
+        slice::from_raw_parts(ptr, input_size)
+    };
+
+    let image = match JxlImage::builder().read(data) {
+        Ok(image) => image,
+        Err(_image) => return std::ptr::null_mut(),
+    };
+
+    let mut output_buffer = match ArenaBuf::new(output_size) {
+        Some(buffer) => buffer,
+        None => return ptr::null(),
+    };
+
+    for keyframe_idx in 0..image.num_loaded_keyframes() {
+        let frame = match image.render_frame(keyframe_idx) {
+            Ok(frame) => frame,
+            Err(_frame) => return std::ptr::null_mut(),
+        };
+
+        let mut stream = frame.stream();
+        let channels = stream.channels() as usize;
+        let mut fb = FrameBuffer::new(
+            stream.width() as usize,
+            stream.height() as usize,
+            channels,
+        );
+        stream.write_to_buffer(fb.buf_mut());
+
+        // Walk one interleaved pixel at a time: Rgba emits R,G,B plus a single
+        // opaque alpha at the requested depth, every other format (Gray, GrayA,
+        // Rgb, extra channels) keeps its channels verbatim.
+        let format = image.pixel_format();
+        for pixel in fb.buf().chunks(channels) {
+            match format {
+                PixelFormat::Rgba => {
+                    for &sample in &pixel[..3] {
+                        if !push_sample(&mut output_buffer, sample, out_bits) {
+                            return std::ptr::null_mut();
+                        }
+                    }
+                    // Fully opaque alpha at the requested output depth.
+                    if !push_sample(&mut output_buffer, 1.0, out_bits) {
+                        return std::ptr::null_mut();
+                    }
+                }
+                _ => {
+                    for &sample in pixel {
+                        if !push_sample(&mut output_buffer, sample, out_bits) {
+                            return std::ptr::null_mut();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    output_buffer.finish()
+}
+
+// Largest reduction we honor. jxl-oxide only renders the downsampling factors
+// {1, 2, 4, 8} and clamps anything larger, so reduction is capped at 3 (factor
+// 8). Clamping here keeps `dimensions_at_level` and `decode_at_level` in
+// agreement — both derive their factor from `reduction_factor` — and bounds the
+// JS-supplied value well below the shift that would overflow a wasm32 `usize`.
+const MAX_REDUCTION: u32 = 3;
+
+// Reduction factor `2^reduction`, clamped to the set jxl-oxide's downsampling
+// actually supports so the shift can never overflow and the reported and
+// rendered dimensions always match.
+fn reduction_factor(reduction: u32) -> usize {
+    1usize << reduction.min(MAX_REDUCTION)
+}
+
+// Dimension of a single axis after applying a 1/2^reduction factor, rounded
+// up so the reduced image still covers the full source extent.
+fn reduced_size(full: usize, reduction: u32) -> usize {
+    full.div_ceil(reduction_factor(reduction)).max(1)
+}
+
+#[no_mangle]
+pub fn decode_at_level(
+    ptr: *mut u8,
+    input_size: usize,
+    reduction: u32,
+    output_size: usize,
+) -> *const u8 {
+    if ptr.is_null() || input_size == 0 || output_size == 0 {
+        return ptr::null();
+    }
+
+    let data: &[u8] = unsafe {
+        slice::from_raw_parts(ptr, input_size)
+    };
+
+    let mut image = match JxlImage::builder().read(data) {
+        Ok(image) => image,
+        Err(_image) => return std::ptr::null_mut(),
+    };
+
+    // Render JXL's progressive structure at 1/2^reduction directly: jxl-oxide
+    // downsamples from the DC pass, which is far cheaper than a full render
+    // followed by a Rust-side average. `reduction_factor` clamps to the set
+    // jxl-oxide supports ({1,2,4,8}), so the factor is always accepted (no
+    // panic) and the rendered dimensions match what `dimensions_at_level`
+    // reported. factor == 1 (reduction == 0) asks for the full-resolution
+    // render, matching decode().
+    image.set_downsampling(reduction_factor(reduction) as u32);
+
+    let frame = match image.render_frame(0) {
+        Ok(frame) => frame,
+        Err(_frame) => return std::ptr::null_mut(),
+    };
+
+    let mut stream = frame.stream();
+    let width = stream.width() as usize;
+    let height = stream.height() as usize;
+    let channels = stream.channels() as usize;
+    let mut fb = FrameBuffer::new(width, height, channels);
+    stream.write_to_buffer(fb.buf_mut());
+
+    let format = image.pixel_format();
+    let mut output_buffer = match ArenaBuf::new(output_size) {
+        Some(buffer) => buffer,
+        None => return ptr::null(),
+    };
+
+    // The stream is already at the reduced resolution, so pack it one pixel at
+    // a time just as decode() does for the full frame: Rgba gets a synthetic
+    // opaque alpha, every other format (Gray, GrayA, Rgb, extra channels) keeps
+    // its channels verbatim.
+    for pixel in fb.buf().chunks(channels) {
+        match format {
             PixelFormat::Rgba => {
-                for pixel in fb.buf() {
-                    let value = (pixel * 255.0).clamp(0.0, 255.0).round() as u8;
+                for &sample in &pixel[..3] {
+                    let value = (sample * 255.0).clamp(0.0, 255.0).round() as u8;
+                    output_buffer.push(value);
+                }
+                output_buffer.push(255);  // Alpha channel set to fully opaque
+            }
+            _ => {
+                for &sample in pixel {
+                    let value = (sample * 255.0).clamp(0.0, 255.0).round() as u8;
                     output_buffer.push(value);
-                    output_buffer.push(255);  // Alpha channel set to fully opaque
                 }
             }
-            _ => return std::ptr::null_mut(),
         }
     }
 
-    // Allocate memory in WASM and return a pointer and length
-    let ptr = output_buffer.as_ptr();
+    output_buffer.finish()
+}
 
-    // Ensure that the memory is not dropped until after we return
-    std::mem::forget(output_buffer);
+// Lanczos kernel radius.
+const LANCZOS_A: f32 = 3.0;
 
-    ptr
+// Normalized sinc, sinc(0) == 1.
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let px = std::f32::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+// 1-D Lanczos window, zero outside |x| < a.
+fn lanczos(x: f32) -> f32 {
+    if x.abs() < LANCZOS_A {
+        sinc(x) * sinc(x / LANCZOS_A)
+    } else {
+        0.0
+    }
+}
+
+// Resample one axis of an interleaved float buffer with separable Lanczos.
+// `src` holds `src_len` samples per channel along the resized axis across
+// `lines` lines. `in_stride` is the distance (in samples) between adjacent
+// positions along the resized axis and `in_step` advances to the next line;
+// `out_stride`/`out_step` place the results so the caller controls whether the
+// output is laid out row- or column-major. Returns a buffer of
+// `dst_len * lines * channels` samples.
+#[allow(clippy::too_many_arguments)]
+fn resample_axis(
+    src: &[f32],
+    src_len: usize,
+    dst_len: usize,
+    lines: usize,
+    channels: usize,
+    in_stride: usize,
+    in_step: usize,
+    out_stride: usize,
+    out_step: usize,
+) -> Vec<f32> {
+    let scale = src_len as f32 / dst_len as f32;
+    let filter_scale = scale.max(1.0);
+    let support = LANCZOS_A * filter_scale;
+
+    // Precompute source indices and normalized weights for each output coord.
+    let mut taps: Vec<(usize, f32)> = Vec::new();
+    let mut offsets: Vec<(usize, usize)> = Vec::with_capacity(dst_len);
+    for o in 0..dst_len {
+        let c = (o as f32 + 0.5) * scale - 0.5;
+        let start = (c - support).floor() as isize;
+        let end = (c + support).ceil() as isize;
+        let tap_start = taps.len();
+        let mut sum = 0.0f32;
+        for s in start..=end {
+            let clamped = s.clamp(0, src_len as isize - 1) as usize;
+            let w = lanczos((s as f32 - c) / filter_scale);
+            if w != 0.0 {
+                taps.push((clamped, w));
+                sum += w;
+            }
+        }
+        if sum == 0.0 {
+            // Guard against a zero weight-sum: fall back to the nearest sample.
+            taps.truncate(tap_start);
+            let nearest = (c.round().clamp(0.0, src_len as f32 - 1.0)) as usize;
+            taps.push((nearest, 1.0));
+            sum = 1.0;
+        }
+        // Normalize this output pixel's weights so they sum to 1.
+        for tap in &mut taps[tap_start..] {
+            tap.1 /= sum;
+        }
+        offsets.push((tap_start, taps.len()));
+    }
+
+    let mut dst = vec![0.0f32; dst_len * lines * channels];
+    for line in 0..lines {
+        for ch in 0..channels {
+            let src_base = line * in_step + ch;
+            let dst_base = line * out_step + ch;
+            for (o, &(tap_start, tap_end)) in offsets.iter().enumerate() {
+                let mut acc = 0.0f32;
+                for &(s, w) in &taps[tap_start..tap_end] {
+                    acc += src[src_base + s * in_stride] * w;
+                }
+                dst[dst_base + o * out_stride] = acc;
+            }
+        }
+    }
+    dst
+}
+
+#[no_mangle]
+pub fn decode_resized(
+    ptr: *mut u8,
+    input_size: usize,
+    dst_w: usize,
+    dst_h: usize,
+    output_size: usize,
+) -> *const u8 {
+    if ptr.is_null() || input_size == 0 || dst_w == 0 || dst_h == 0 || output_size == 0 {
+        return ptr::null();
+    }
+
+    let data: &[u8] = unsafe {
+        slice::from_raw_parts(ptr, input_size)
+    };
+
+    let image = match JxlImage::builder().read(data) {
+        Ok(image) => image,
+        Err(_image) => return std::ptr::null_mut(),
+    };
+
+    let frame = match image.render_frame(0) {
+        Ok(frame) => frame,
+        Err(_frame) => return std::ptr::null_mut(),
+    };
+
+    let mut stream = frame.stream();
+    let src_w = stream.width() as usize;
+    let src_h = stream.height() as usize;
+    let channels = stream.channels() as usize;
+    let mut fb = FrameBuffer::new(src_w, src_h, channels);
+    stream.write_to_buffer(fb.buf_mut());
+
+    // Resize horizontally: one line per source row, axis = columns. Output
+    // stays row-major (dst_w columns per row).
+    let horizontal = resample_axis(
+        fb.buf(),
+        src_w,
+        dst_w,
+        src_h,
+        channels,
+        channels,
+        src_w * channels,
+        channels,
+        dst_w * channels,
+    );
+    // Resize vertically: one line per output column, axis = rows. Place results
+    // back into row-major order so packing below sees (row, col) layout.
+    let resized = resample_axis(
+        &horizontal,
+        src_h,
+        dst_h,
+        dst_w,
+        channels,
+        dst_w * channels,
+        channels,
+        dst_w * channels,
+        channels,
+    );
+
+    let mut output_buffer = match ArenaBuf::new(output_size) {
+        Some(buffer) => buffer,
+        None => return ptr::null(),
+    };
+    match image.pixel_format() {
+        PixelFormat::Rgba => {
+            // Walk one interleaved pixel at a time and emit R,G,B plus a single
+            // opaque alpha, matching decode_region/decode_at_level's layout.
+            for pixel in resized.chunks(channels) {
+                for &sample in &pixel[..3] {
+                    let value = (sample * 255.0).clamp(0.0, 255.0).round() as u8;
+                    output_buffer.push(value);
+                }
+                output_buffer.push(255);  // Alpha channel set to fully opaque
+            }
+        }
+        // Every other format — Gray, GrayA, Rgb, and images with extra channels
+        // — keeps its resampled channels verbatim; only Rgba injects an alpha.
+        _ => {
+            for pixel in &resized {
+                let value = (pixel * 255.0).clamp(0.0, 255.0).round() as u8;
+                output_buffer.push(value);
+            }
+        }
+    }
+
+    output_buffer.finish()
+}
+
+#[no_mangle]
+pub fn decode_region(
+    ptr: *mut u8,
+    input_size: usize,
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+    output_size: usize,
+) -> *const u8 {
+    if ptr.is_null() || input_size == 0 || w == 0 || h == 0 || output_size == 0 {
+        return ptr::null();
+    }
+
+    let data: &[u8] = unsafe {
+        slice::from_raw_parts(ptr, input_size)
+    };
+
+    let image = match JxlImage::builder().read(data) {
+        Ok(image) => image,
+        Err(_image) => return std::ptr::null_mut(),
+    };
+
+    // Only the first keyframe is needed to satisfy a single tile request.
+    let frame = match image.render_frame(0) {
+        Ok(frame) => frame,
+        Err(_frame) => return std::ptr::null_mut(),
+    };
+
+    let mut stream = frame.stream();
+    let frame_width = stream.width() as usize;
+    let frame_height = stream.height() as usize;
+    let channels = stream.channels() as usize;
+    let mut fb = FrameBuffer::new(frame_width, frame_height, channels);
+    stream.write_to_buffer(fb.buf_mut());
+
+    // Validate the requested window against the dimensions we actually index
+    // with (the rendered frame), not the header, so we can never index past
+    // the buffer if the two ever disagree. Use checked_add so large caller
+    // coordinates can't wrap the comparison on wasm32's 32-bit usize.
+    let (end_x, end_y) = match (x.checked_add(w), y.checked_add(h)) {
+        (Some(end_x), Some(end_y)) if end_x <= frame_width && end_y <= frame_height => {
+            (end_x, end_y)
+        }
+        _ => return std::ptr::null_mut(),
+    };
+
+    let format = image.pixel_format();
+    let mut output_buffer = match ArenaBuf::new(output_size) {
+        Some(buffer) => buffer,
+        None => return ptr::null(),
+    };
+
+    // Copy only the pixels inside the window, walking row-by-row with the
+    // source stride. The whole frame has already been rendered into `fb`;
+    // only the window's pixels are packed into the output.
+    let buf = fb.buf();
+    for row in y..end_y {
+        for col in x..end_x {
+            let base = (row * frame_width + col) * channels;
+            match format {
+                PixelFormat::Gray => {
+                    let value = (buf[base] * 255.0).clamp(0.0, 255.0).round() as u8;
+                    output_buffer.push(value);
+                }
+                PixelFormat::Rgb => {
+                    for c in 0..3 {
+                        let value = (buf[base + c] * 255.0).clamp(0.0, 255.0).round() as u8;
+                        output_buffer.push(value);
+                    }
+                }
+                PixelFormat::Rgba => {
+                    for c in 0..3 {
+                        let value = (buf[base + c] * 255.0).clamp(0.0, 255.0).round() as u8;
+                        output_buffer.push(value);
+                    }
+                    output_buffer.push(255);  // Alpha channel set to fully opaque
+                }
+                _ => return std::ptr::null_mut(),
+            }
+        }
+    }
+
+    output_buffer.finish()
 }
 
 